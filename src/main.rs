@@ -1,11 +1,133 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
 use chrono::prelude::*;
 use nannou::prelude::*;
+use nannou::wgpu;
 
 
 fn main() {
-    nannou::app(model).update(update).run();
+    nannou::app(model).update(update).exit(exit).run();
+}
+
+// Buffers completed GPU readbacks by frame index and only ever writes them to
+// `stdin` in strictly increasing order, since snapshot callbacks run on the
+// capturer's thread pool and can complete out of submission order.
+struct StdinWriter {
+    stdin: ChildStdin,
+    pending: BTreeMap<u64, Vec<u8>>,
+    next_write: u64,
+}
+
+impl StdinWriter {
+    fn submit(&mut self, frame_index: u64, bytes: Vec<u8>) {
+        self.pending.insert(frame_index, bytes);
+        while let Some(bytes) = self.pending.remove(&self.next_write) {
+            self.stdin
+                .write_all(&bytes)
+                .expect("failed to write frame to ffmpeg stdin");
+            self.next_write += 1;
+        }
+    }
+}
+
+// Streams raw RGBA frames into a spawned `ffmpeg` process over stdin, which
+// encodes them straight to an mp4. Avoids dumping thousands of PNGs to disk.
+// If `ffmpeg` isn't on `PATH`, recording is simply disabled rather than
+// taking the whole sketch down.
+struct Recorder {
+    texture_capturer: wgpu::TextureCapturer,
+    device_queue_pair: Arc<wgpu::DeviceQueuePair>,
+    child: Child,
+    writer: Arc<Mutex<StdinWriter>>,
+    next_frame_index: AtomicU64,
+}
+
+impl Recorder {
+    fn new(win_w: u32, win_h: u32, out_path: &Path, device_queue_pair: Arc<wgpu::DeviceQueuePair>) -> Option<Self> {
+        let file_path = out_path.with_extension("mp4");
+        std::fs::create_dir_all(file_path.parent().unwrap())
+            .expect("failed to create `out` dir");
+
+        let mut child = match Command::new("ffmpeg")
+            .args([
+                "-y",
+                "-f", "rawvideo",
+                "-pix_fmt", "rgba",
+                "-s", &format!("{}x{}", win_w, win_h),
+                "-r", "60",
+                "-i", "-",
+                "-c:v", "libx264",
+                "-pix_fmt", "yuv420p",
+            ])
+            .arg(&file_path)
+            .stdin(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => {
+                eprintln!("warning: couldn't start `ffmpeg` ({err}) - video recording disabled");
+                return None;
+            }
+        };
+
+        let stdin = child.stdin.take().expect("failed to open ffmpeg stdin");
+        let writer = Arc::new(Mutex::new(StdinWriter {
+            stdin,
+            pending: BTreeMap::new(),
+            next_write: 0,
+        }));
+
+        Some(Recorder {
+            texture_capturer: wgpu::TextureCapturer::default(),
+            device_queue_pair,
+            child,
+            writer,
+            next_frame_index: AtomicU64::new(0),
+        })
+    }
+
+    fn write_frame(&self, texture: &wgpu::Texture) {
+        let device = self.device_queue_pair.device();
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("recorder frame capture"),
+            });
+        let snapshot = self.texture_capturer.capture(device, &mut encoder, texture);
+        self.device_queue_pair.queue().submit(Some(encoder.finish()));
+
+        let frame_index = self.next_frame_index.fetch_add(1, Ordering::SeqCst);
+        let writer = self.writer.clone();
+        snapshot
+            .read(move |result| {
+                let image = result.expect("failed to map captured frame");
+                let bytes = image.to_owned().into_raw();
+                writer.lock().unwrap().submit(frame_index, bytes);
+            })
+            .unwrap();
+    }
+
+    fn finish(mut self) {
+        // make sure every in-flight snapshot callback has actually run
+        // before we close the pipe, or trailing frames get dropped and a
+        // callback still holding `writer` can keep ffmpeg from ever seeing EOF
+        self.texture_capturer
+            .await_active_snapshots(self.device_queue_pair.device())
+            .expect("failed to await in-flight frame captures");
+        self.writer.lock().unwrap().stdin.flush().ok();
+        drop(self.writer);
+        self.child.wait().expect("ffmpeg did not exit cleanly");
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Attractor {
+    DeJong,
+    Clifford,
 }
 
 struct Point {
@@ -15,6 +137,13 @@ struct Point {
     max_x: f32,
     min_y: f32,
     max_y: f32,
+    raw_x: f32,
+    raw_y: f32,
+    attractor: Attractor,
+    a: f32,
+    b: f32,
+    c: f32,
+    d: f32,
 }
 
 impl Point {
@@ -24,21 +153,60 @@ impl Point {
         let min_y = boundary.bottom();
         let max_y = boundary.top();
 
-        Point {
-            x, 
-            y, 
-            min_x, 
-            max_x, 
-            min_y, 
+        let mut point = Point {
+            x,
+            y,
+            min_x,
+            max_x,
+            min_y,
             max_y,
-        }
+            raw_x: 0.1,
+            raw_y: 0.1,
+            attractor: Attractor::DeJong,
+            a: 0.0,
+            b: 0.0,
+            c: 0.0,
+            d: 0.0,
+        };
+        point.reroll();
+        point
     }
 
-    fn update(&mut self, app: &App) {
-        let sin1 = (app.time / 1.4).sin();
-        let sin2 = (app.time / 2.0).sin();
-        self.x = map_range(sin1, -1.0, 1.0, self.min_x, self.max_x);
-        self.y = map_range(sin2, -1.0, 1.0, self.min_y, self.max_y); 
+    // pick a new attractor kind and parameters, restarting the iterate
+    fn reroll(&mut self) {
+        self.attractor = if random_f32() < 0.5 {
+            Attractor::DeJong
+        } else {
+            Attractor::Clifford
+        };
+        self.a = random_range(-3.0, 3.0);
+        self.b = random_range(-3.0, 3.0);
+        self.c = random_range(-3.0, 3.0);
+        self.d = random_range(-3.0, 3.0);
+        self.raw_x = 0.1;
+        self.raw_y = 0.1;
+    }
+
+    fn update(&mut self, _app: &App) {
+        let (x, y) = match self.attractor {
+            Attractor::DeJong => (
+                (self.a * self.raw_y).sin() - (self.b * self.raw_x).cos(),
+                (self.c * self.raw_x).sin() - (self.d * self.raw_y).cos(),
+            ),
+            Attractor::Clifford => (
+                (self.a * self.raw_y).sin() + self.c * (self.a * self.raw_x).cos(),
+                (self.b * self.raw_x).sin() + self.d * (self.b * self.raw_y).cos(),
+            ),
+        };
+        self.raw_x = x;
+        self.raw_y = y;
+
+        // Clifford's range grows with |c|/|d|, so clamp before mapping to
+        // keep the target from wandering off past the window bounds.
+        let clamped_x = self.raw_x.clamp(-2.0, 2.0);
+        let clamped_y = self.raw_y.clamp(-2.0, 2.0);
+        self.x = map_range(clamped_x, -2.0, 2.0, self.min_x, self.max_x);
+        self.y = map_range(clamped_y, -2.0, 2.0, self.min_y, self.max_y);
     }
 }
 
@@ -86,33 +254,170 @@ impl Node {
     }
 }
 
+#[derive(Clone, Copy)]
+enum Layout {
+    Grid,
+    Spiral,
+}
+
+const NODE_ROWS: usize = 30;
+const NODE_COLS: usize = 30;
+const NODE_RADIUS: f32 = 20.0;
+
 struct Model {
     _window: window::Id,
     nodes: Vec<Node>,
     point: Point,
-    out_path: PathBuf,
+    layout: Layout,
+    walkers: Vec<Vec2>,
+    clustered: Vec<Vec2>,
+    dla_step: f32,
+    dla_radius: f32,
+    chaikin_iterations: usize,
+    chaikin_closed: bool,
+    trail_alpha: f32,
+    recorder: Option<Recorder>,
+    fire: Vec<u8>,
+    fire_width: usize,
+    fire_height: usize,
+    fire_hue: f32,
 }
 
 fn model(app: &App) -> Model {
-    let _window = app.new_window().size(1200, 1200).view(view).build().unwrap();
+    let _window = app
+        .new_window()
+        .size(1200, 1200)
+        .view(view)
+        .key_pressed(key_pressed)
+        .build()
+        .unwrap();
 
     // create point
     let point = Point::new(-50.0, 0.0, app.window_rect().pad(300.0));
 
     // create nodes
-    let cols = 30;
-    let rows = 30;
-    let radius = 20.0;
-    let nodes = create_nodes(rows, cols, app.window_rect(), &point, radius);
+    let layout = Layout::Spiral;
+    let nodes = create_nodes(
+        NODE_ROWS,
+        NODE_COLS,
+        app.window_rect(),
+        &point,
+        NODE_RADIUS,
+        layout,
+    );
 
     // output path to save frames
     let out_path = create_output_path(app);
+    let device_queue_pair = app.main_window().device_queue_pair().clone();
+    let recorder = Recorder::new(1200, 1200, &out_path, device_queue_pair);
+
+    // DLA: a single seed at the origin, walkers scattered through the window
+    let dla_radius = 8.0;
+    let clustered = vec![vec2(0.0, 0.0)];
+    let walkers = create_walkers(400, app.window_rect().pad(200.0));
+
+    // doom fire background grid
+    let fire_width = 80;
+    let fire_height = 80;
 
     Model {
-        _window, 
-        nodes, 
-        point, 
-        out_path
+        _window,
+        nodes,
+        point,
+        layout,
+        walkers,
+        clustered,
+        dla_step: 1.5,
+        dla_radius,
+        chaikin_iterations: 3,
+        chaikin_closed: true,
+        trail_alpha: 0.05,
+        recorder,
+        fire: vec![0u8; fire_width * fire_height],
+        fire_width,
+        fire_height,
+        fire_hue: 0.0,
+    }
+}
+
+fn exit(_app: &App, model: Model) {
+    if let Some(recorder) = model.recorder {
+        recorder.finish();
+    }
+}
+
+// R re-rolls the attractor, L toggles the node layout and rebuilds the nodes
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+    if key == Key::R {
+        model.point.reroll();
+    }
+    if key == Key::L {
+        model.layout = match model.layout {
+            Layout::Grid => Layout::Spiral,
+            Layout::Spiral => Layout::Grid,
+        };
+        model.nodes = create_nodes(
+            NODE_ROWS,
+            NODE_COLS,
+            app.window_rect(),
+            &model.point,
+            NODE_RADIUS,
+            model.layout,
+        );
+    }
+}
+
+fn create_walkers(n: usize, win: Rect) -> Vec<Vec2> {
+    (0..n)
+        .map(|_| {
+            vec2(
+                random_range(win.left(), win.right()),
+                random_range(win.bottom(), win.top()),
+            )
+        })
+        .collect()
+}
+
+fn vec2_range(lo: f32, hi: f32) -> Vec2 {
+    vec2(random_range(lo, hi), random_range(lo, hi))
+}
+
+// Advance the DLA simulation by one step: jitter every walker, then freeze
+// any walker that lands within `model.dla_radius` of the cluster.
+fn update_dla(model: &mut Model) {
+    for walker in model.walkers.iter_mut() {
+        *walker += vec2_range(-1.0, 1.0) * model.dla_step;
+    }
+
+    let radius = model.dla_radius;
+    let mut frozen = Vec::new();
+    model.walkers.retain(|walker| {
+        let mut hit = false;
+        for p in model.clustered.iter() {
+            if p.x > walker.x + radius {
+                break;
+            }
+            if p.x < walker.x - radius {
+                continue;
+            }
+            if walker.distance(*p) < radius {
+                hit = true;
+                break;
+            }
+        }
+        if hit {
+            frozen.push(*walker);
+        }
+        !hit
+    });
+
+    for pos in frozen {
+        let idx = model
+            .clustered
+            .binary_search_by(|p| p.x.partial_cmp(&pos.x).unwrap())
+            .unwrap_or_else(|i| i);
+        model.clustered.insert(idx, pos);
+        model.nodes.push(Node::new(pos, pos.x, pos.y, model.dla_radius));
     }
 }
 
@@ -124,7 +429,25 @@ fn create_output_path(app: &App) -> PathBuf {
         .join(time_str)
 }
 
-fn create_nodes(rows: usize, cols: usize, win: Rect, target: &Point, radius: f32) -> Vec<Node> {
+// fraction of a full turn stepped per node: 1/phi of a turn (about 222.5 deg),
+// i.e. the golden angle (about 137.5 deg) measured the other way round
+const GOLDEN_ANGLE_FRAC: f32 = 0.618_034;
+
+fn create_nodes(
+    rows: usize,
+    cols: usize,
+    win: Rect,
+    target: &Point,
+    radius: f32,
+    layout: Layout,
+) -> Vec<Node> {
+    match layout {
+        Layout::Grid => create_nodes_grid(rows, cols, win, target, radius),
+        Layout::Spiral => create_nodes_spiral(rows * cols, win, target, radius),
+    }
+}
+
+fn create_nodes_grid(rows: usize, cols: usize, win: Rect, target: &Point, radius: f32) -> Vec<Node> {
     let win_p = win.pad(200.0);
     let x_gap = (win_p.right() - win_p.left()) / (cols as f32 - 1.0);
     let y_gap = (win_p.top() - win_p.bottom()) / (rows as f32 - 1.0);
@@ -149,16 +472,136 @@ fn create_nodes(rows: usize, cols: usize, win: Rect, target: &Point, radius: f32
     nodes
 }
 
+fn create_nodes_spiral(n: usize, win: Rect, target: &Point, radius: f32) -> Vec<Node> {
+    let win_p = win.pad(200.0);
+    let max_dim = win_p.right().min(win_p.top());
+    let theta_max = (n.max(1) - 1) as f32 * TAU * GOLDEN_ANGLE_FRAC;
+    let scale = max_dim / theta_max.sqrt();
+    let mut nodes = Vec::new();
+
+    for i in 0..n {
+        let theta = i as f32 * TAU * GOLDEN_ANGLE_FRAC;
+        let r = scale * theta.sqrt();
+        let x_center = r * theta.cos();
+        let y_center = r * theta.sin();
+        let rad = vec2(x_center, y_center).angle_between(vec2(target.x, target.y));
+        let x = x_center + rad.sin() * radius;
+        let y = y_center + rad.cos() * radius;
+        let node = Node::new(
+            Vec2::new(x_center, y_center),
+            x,
+            y,
+            radius
+        );
+        nodes.push(node);
+    }
+    nodes
+}
+
+// Chaikin corner-cutting: replace each edge `P_i -> P_{i+1}` with the two
+// points that are a quarter and three-quarters of the way along it, rounding
+// the polyline's corners. `closed` wraps the last point back to the first.
+fn chaikin_smooth(points: &[Vec2], iterations: usize, closed: bool) -> Vec<Vec2> {
+    let mut pts = points.to_vec();
+
+    for _ in 0..iterations {
+        if pts.len() < 3 {
+            break;
+        }
+        let mut next = Vec::with_capacity(pts.len() * 2);
+        let edges = if closed { pts.len() } else { pts.len() - 1 };
+
+        if !closed {
+            next.push(pts[0]);
+        }
+        for i in 0..edges {
+            let p = pts[i];
+            let q = pts[(i + 1) % pts.len()];
+            next.push(p * 0.75 + q * 0.25);
+            next.push(p * 0.25 + q * 0.75);
+        }
+        if !closed {
+            next.push(*pts.last().unwrap());
+        }
+        pts = next;
+    }
+    pts
+}
+
+// classic "Doom fire": seed the bottom row each frame, then let every cell
+// above it drift upward with a little random sideways jitter and cooling.
+fn update_fire(model: &mut Model) {
+    let width = model.fire_width;
+    let height = model.fire_height;
+
+    for x in 0..width {
+        let idx = (height - 1) * width + x;
+        model.fire[idx] = 255;
+    }
+
+    for src_index in width..(width * height) {
+        let rand = random_range(0u8, 3);
+        let row = src_index / width;
+        let col = src_index % width;
+
+        let drift_col = col as i64 - (rand & 1) as i64 + 1;
+        if row == 0 || drift_col < 0 || drift_col as usize >= width {
+            continue;
+        }
+        let dst_index = (row - 1) * width + drift_col as usize;
+
+        let src_heat = model.fire[src_index];
+        model.fire[dst_index] = src_heat.saturating_sub(rand & 1);
+    }
+}
+
+// black -> red -> orange -> yellow -> white, rotatable via `hue`
+fn fire_color(heat: u8, hue: f32) -> Hsv {
+    let t = heat as f32 / 255.0;
+    let h = hue + t * 60.0;
+    let s = (1.0 - t.powf(3.0)).max(0.0);
+    hsv(h / 360.0, s, t)
+}
+
 fn update(app: &App, model: &mut Model, _update: Update) {
-    model.point.update(&app);
+    model.point.update(app);
     for i in 0..model.nodes.len() {
         model.nodes[i].update(&model.point);
     }
+    update_dla(model);
+    update_fire(model);
 }
 
 fn view(app: &App, model: &Model, frame: Frame) {
     let draw = app.draw();
-    draw.background().color(BLACK);
+    let win = app.window_rect();
+    // a solid clear on the first frame, then a translucent overlay so
+    // previous frames fade out instead of vanishing, leaving light trails
+    if frame.nth() == 0 {
+        draw.background().color(BLACK);
+    } else {
+        draw.rect()
+            .wh(win.wh())
+            .color(srgba(0.0, 0.0, 0.0, model.trail_alpha));
+    }
+    // doom fire background
+    let cell_w = win.w() / model.fire_width as f32;
+    let cell_h = win.h() / model.fire_height as f32;
+    for y in 0..model.fire_height {
+        for x in 0..model.fire_width {
+            let heat = model.fire[y * model.fire_width + x];
+            if heat == 0 {
+                continue;
+            }
+            let cell_x = win.left() + (x as f32 + 0.5) * cell_w;
+            let cell_y = win.top() - (y as f32 + 0.5) * cell_h;
+            draw.rect()
+                .x_y(cell_x, cell_y)
+                .w_h(cell_w, cell_h)
+                .color(fire_color(heat, model.fire_hue));
+        }
+    }
+
     // draw.ellipse().x_y(model.point.x, model.point.y).radius(30.0).color(RED);
     model.nodes.iter().for_each(|node| {
         draw.ellipse()
@@ -167,19 +610,18 @@ fn view(app: &App, model: &Model, frame: Frame) {
             .color(node.color);
     });
 
+    // smooth ribbon through the node positions
+    let raw: Vec<Vec2> = model.nodes.iter().map(|n| vec2(n.x, n.y)).collect();
+    let smoothed = chaikin_smooth(&raw, model.chaikin_iterations, model.chaikin_closed);
+    draw.polyline()
+        .weight(2.0)
+        .points(smoothed)
+        .color(srgba(1.0, 1.0, 1.0, 0.3));
+
     draw.to_frame(app, &frame).unwrap();
 
-    // capture frames (max 1000 saved)
-    // deactivate to make output smoother
-    if frame.nth() < 1000 {
-        let file_path = captured_frame_path(&model, &frame);
-        app.main_window().capture_frame(file_path);
+    // stream the frame into the recording ffmpeg pipe, if it's running
+    if let Some(recorder) = &model.recorder {
+        recorder.write_frame(frame.texture());
     }
-}
-
-fn captured_frame_path(model: &Model, frame: &Frame) -> std::path::PathBuf {
-    // Create a path that we want to save this frame to.
-    model.out_path
-        .join(format!("{:03}", frame.nth()))
-        .with_extension("png")
 }
\ No newline at end of file